@@ -0,0 +1,83 @@
+use uuid::Uuid;
+
+use crate::{Priority, Status, Todo};
+
+/// Parses a Markdown task list (`- [ ] text` / `- [x] text`) into todos,
+/// generating a fresh id and the matching `Status` for each line.
+pub(crate) fn parse_markdown(content: &str) -> Vec<Todo> {
+    content.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+
+            if let Some(text) = line.strip_prefix("- [x]").or_else(|| line.strip_prefix("- [X]")) {
+                Some(new_todo(text, Status::Done))
+            } else {
+                line.strip_prefix("- [ ]").map(|text| new_todo(text, Status::Incomplete))
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn to_markdown(todos: &[Todo]) -> String {
+    todos.iter()
+        .map(|t| format!("- [{}] {}", if t.status == Status::Done { "x" } else { " " }, t.todo))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses a plain one-line-per-todo file; every line becomes an incomplete todo.
+pub(crate) fn parse_plaintext(content: &str) -> Vec<Todo> {
+    content.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| new_todo(line, Status::Incomplete))
+        .collect()
+}
+
+pub(crate) fn to_plaintext(todos: &[Todo]) -> String {
+    todos.iter()
+        .map(|t| t.todo.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn new_todo(text: &str, status: Status) -> Todo {
+    Todo {
+        id: Uuid::new_v4().to_string(),
+        todo: text.trim().to_string(),
+        status,
+        due: None,
+        priority: Priority::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_markdown_checkboxes() {
+        let todos = parse_markdown("- [ ] buy milk\n- [x] walk the dog\nnot a todo");
+
+        assert_eq!(2, todos.len());
+        assert_eq!(Status::Incomplete, todos[0].status);
+        assert_eq!("buy milk", todos[0].todo);
+        assert_eq!(Status::Done, todos[1].status);
+        assert_eq!("walk the dog", todos[1].todo);
+    }
+
+    #[test]
+    fn parses_plaintext_lines() {
+        let todos = parse_plaintext("buy milk\n\nwalk the dog\n");
+
+        assert_eq!(2, todos.len());
+        assert!(todos.iter().all(|t| t.status == Status::Incomplete));
+    }
+
+    #[test]
+    fn round_trips_markdown() {
+        let todos = parse_markdown("- [ ] buy milk\n- [x] walk the dog");
+
+        assert_eq!("- [ ] buy milk\n- [x] walk the dog", to_markdown(&todos));
+    }
+}
@@ -1,55 +1,202 @@
 use std::fs::File;
 use std::path::Path;
-use std::io::Error;
+use std::sync::mpsc::{channel, Receiver};
 use uuid::Uuid;
 use serde::{Serialize, Deserialize};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use sha2::{Digest, Sha256};
+use chrono::{DateTime, Utc};
+
+mod format;
+mod rpc;
+pub use rpc::serve;
 
 pub struct Config {
-    pub path: String
+    pub path: String,
+    pub watch: bool,
+    pub remote: Option<String>
 }
 
+/// The error type of the todo store and todo operations: reading and
+/// parsing the store, adding and completing todos, and picking a menu
+/// option. Bad input, a missing todo or a corrupt store is reported here
+/// instead of panicking or being silently swallowed. Remote sync, file
+/// watching and the JSON-RPC server report their other failures through
+/// `Box<dyn std::error::Error>`.
 #[derive(Debug)]
-struct TodoError(String);
+pub enum TdoError {
+    Io(std::io::Error),
+    Deserialize { path: String, source: serde_json::Error },
+    EmptyTodo,
+    TodoNotFound(String),
+    InvalidOption
+}
 
-impl std::fmt::Display for TodoError {
+impl std::fmt::Display for TdoError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "There is an error: {}", self.0)
+        match self {
+            TdoError::Io(e) => write!(f, "I/O error: {}", e),
+            TdoError::Deserialize { path, source } => write!(f, "could not parse todos in {}: {}", path, source),
+            TdoError::EmptyTodo => write!(f, "todo cannot be empty"),
+            TdoError::TodoNotFound(id) => write!(f, "todo not found: {}", id),
+            TdoError::InvalidOption => write!(f, "invalid option selected")
+        }
+    }
+}
+
+impl std::error::Error for TdoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TdoError::Io(e) => Some(e),
+            TdoError::Deserialize { source, .. } => Some(source),
+            _ => None
+        }
+    }
+}
+
+impl From<std::io::Error> for TdoError {
+    fn from(e: std::io::Error) -> Self {
+        TdoError::Io(e)
     }
 }
 
-impl std::error::Error for TodoError {}
+pub(crate) const DEFAULT_LIST_NAME: &str = "default";
+
+enum Action {
+    AddTodo,
+    CompleteTodo,
+    CreateList,
+    SwitchList,
+    MoveTodo,
+    ImportTodos,
+    ExportTodos,
+    ListByDueDate,
+    ListByPriority,
+    ListOverdue
+}
 
 pub fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
-    let mut todos = FileProvider::read_todos(&config.path)?;
+    let remote = config.remote.as_ref().map(|endpoint| RemoteProvider::new(endpoint.clone()));
+
+    if let Some(remote) = &remote {
+        match sync_with_remote(&config.path, remote)? {
+            SyncOutcome::UpToDate => {},
+            SyncOutcome::Pushed => println!("Pushed local changes to remote"),
+            SyncOutcome::Pulled => println!("Pulled remote changes"),
+            SyncOutcome::Conflict => resolve_conflict(&config.path, remote)?
+        }
+    }
+
+    let mut tdo = load_todos(&config.path)?;
+    let mut active = 0;
+
+    let watcher = if config.watch {
+        Some(watch_file(&config.path)?)
+    } else {
+        None
+    };
 
     loop {
-        let unfinished = get_unfinished_todos(&todos)?;
+        if let Some((_, rx)) = &watcher {
+            if reload_pending(rx) {
+                tdo = load_todos(&config.path)?;
+                if active >= tdo.lists.len() {
+                    active = 0;
+                }
+                println!("Reloaded todos from disk");
+            }
+        }
 
-        let options = list_options(unfinished.len());
+        let unfinished = get_unfinished_todos(&tdo.lists[active].todos)?;
 
-        let msg = format!("You have {} incomplete todos in your todo list", unfinished.len());
+        let menu = build_menu(unfinished.len(), tdo.lists.len());
+        let labels: Vec<&str> = menu.iter().map(|option| option.0).collect();
 
-        let response_option = get_option(&options, Some(msg.as_str()));
+        let msg = format!(
+            "List \"{}\" has {} incomplete todos ({} incomplete across all lists)",
+            tdo.lists[active].name,
+            unfinished.len(),
+            get_unfinished_count(&tdo, active, true)
+        );
 
-        match response_option {
-            0 => match add_todo(&mut todos) {
-                Ok(todos) => {
-                    FileProvider::write_todos(todos, &config.path)?;
-                    println!("Todo added");
-                },
-                Err(e) => println!("{:?}", e)
+        let response_option = get_option(&labels, Some(msg.as_str()));
+
+        match menu[response_option].1 {
+            Action::AddTodo => match add_todo(&mut tdo.lists[active].todos) {
+                Ok(_) => println!("Todo added"),
+                Err(e) => println!("{}", e)
             },
-            1 => {
+            Action::CompleteTodo => {
                 let todo_id = get_todo_to_complete(&unfinished)?;
-                complete_todo(&todo_id, &mut todos).unwrap();
+                complete_todo(&todo_id, &mut tdo.lists[active].todos)?;
+            },
+            Action::CreateList => create_list(&mut tdo),
+            Action::SwitchList => active = switch_active_list(&tdo),
+            Action::MoveTodo => move_todo(&mut tdo, active)?,
+            Action::ImportTodos => import_todos(&mut tdo, active)?,
+            Action::ExportTodos => export_todos(&tdo, active)?,
+            Action::ListByDueDate => {
+                print_todos("Sorted by due date", &sorted_todos(&tdo.lists[active].todos, SortBy::DueDate));
+                continue;
             },
-            _ => break
+            Action::ListByPriority => {
+                print_todos("Sorted by priority", &sorted_todos(&tdo.lists[active].todos, SortBy::Priority));
+                continue;
+            },
+            Action::ListOverdue => {
+                print_todos("Overdue", &overdue_todos(&tdo.lists[active].todos));
+                continue;
+            }
         }
 
-        FileProvider::write_todos(&todos, &config.path)?;
+        FileProvider::write_todos(&tdo, &config.path)?;
+
+        if let Some(remote) = &remote {
+            match sync_with_remote(&config.path, remote)? {
+                SyncOutcome::UpToDate | SyncOutcome::Pushed => {},
+                SyncOutcome::Pulled => {
+                    tdo = load_todos(&config.path)?;
+                    if active >= tdo.lists.len() {
+                        active = 0;
+                    }
+                    println!("Remote had newer changes, pulled them before continuing");
+                },
+                SyncOutcome::Conflict => {
+                    resolve_conflict(&config.path, remote)?;
+                    tdo = load_todos(&config.path)?;
+                    if active >= tdo.lists.len() {
+                        active = 0;
+                    }
+                }
+            }
+        }
     }
-    
-    Ok(())
+}
+
+fn build_menu(unfinished_count: usize, list_count: usize) -> Vec<(&'static str, Action)> {
+    let mut menu = vec![("add a new todo", Action::AddTodo)];
+
+    if unfinished_count > 0 {
+        menu.push(("complete a todo", Action::CompleteTodo));
+    }
+
+    menu.push(("create a new list", Action::CreateList));
+
+    if list_count > 1 {
+        menu.push(("switch active list", Action::SwitchList));
+    }
+
+    if unfinished_count > 0 && list_count > 1 {
+        menu.push(("move a todo to another list", Action::MoveTodo));
+    }
+
+    menu.push(("import todos from a file", Action::ImportTodos));
+    menu.push(("export todos to a file", Action::ExportTodos));
+    menu.push(("list todos sorted by due date", Action::ListByDueDate));
+    menu.push(("list todos sorted by priority", Action::ListByPriority));
+    menu.push(("list overdue todos", Action::ListOverdue));
+
+    menu
 }
 
 fn get_option<T: std::fmt::Display>(options: &Vec<T>, msg: Option<&str>) -> usize {
@@ -61,7 +208,7 @@ fn get_option<T: std::fmt::Display>(options: &Vec<T>, msg: Option<&str>) -> usiz
         for (idx, option) in options.iter().enumerate() {
             ops.push(format!("{}: {}", idx+1, option));
         }
-    
+
         let option = prompt(ops.join("\n").as_str());
 
         match option.parse::<usize>() {
@@ -72,84 +219,435 @@ fn get_option<T: std::fmt::Display>(options: &Vec<T>, msg: Option<&str>) -> usiz
     }
 }
 
-trait DataProvider {
-    fn read_todos(path: &String) -> Result<Vec<Todo>, Box<dyn std::error::Error>>;
+type WatchHandle = (RecommendedWatcher, Receiver<notify::Result<notify::Event>>);
+
+fn watch_file(path: &str) -> Result<WatchHandle, Box<dyn std::error::Error>> {
+    let (tx, rx) = channel();
+
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })?;
+
+    watcher.watch(Path::new(path), RecursiveMode::NonRecursive)?;
+
+    Ok((watcher, rx))
+}
+
+fn reload_pending(rx: &Receiver<notify::Result<notify::Event>>) -> bool {
+    let mut changed = false;
+
+    while let Ok(Ok(event)) = rx.try_recv() {
+        if event.kind.is_modify() {
+            changed = true;
+        }
+    }
+
+    changed
+}
+
+pub(crate) trait DataProvider {
+    fn read_todos(path: &String) -> Result<Tdo, TdoError>;
 
-    fn write_todos(ts: &Vec<Todo>, path: &String) -> Result<(), Box<dyn std::error::Error>>;
+    fn write_todos(tdo: &Tdo, path: &String) -> Result<(), TdoError>;
 }
 
-struct FileProvider {}
+enum FileFormat {
+    Json,
+    Markdown,
+    Plaintext
+}
+
+fn file_format(path: &str) -> FileFormat {
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("md") => FileFormat::Markdown,
+        Some("txt") => FileFormat::Plaintext,
+        _ => FileFormat::Json
+    }
+}
+
+fn all_todos(tdo: &Tdo) -> Vec<Todo> {
+    tdo.lists.iter().flat_map(|l| l.todos.clone()).collect()
+}
+
+pub(crate) struct FileProvider {}
 
 impl DataProvider for FileProvider {
-    fn read_todos(path: &String) -> Result<Vec<Todo>, Box<dyn std::error::Error>> {
+    fn read_todos(path: &String) -> Result<Tdo, TdoError> {
         if !Path::new(path).exists() {
             File::create(path)?;
         }
 
-        let file_content = std::fs::read_to_string(path).unwrap();
+        let file_content = std::fs::read_to_string(path)?;
+
+        let mut tdo: Tdo = match file_format(path) {
+            FileFormat::Json if file_content.trim().is_empty() => Tdo::default(),
+            FileFormat::Json => serde_json::from_str(&file_content)
+                .map_err(|source| TdoError::Deserialize { path: path.clone(), source })?,
+            FileFormat::Markdown => Tdo {
+                lists: vec![TodoList { name: String::from(DEFAULT_LIST_NAME), todos: format::parse_markdown(&file_content) }]
+            },
+            FileFormat::Plaintext => Tdo {
+                lists: vec![TodoList { name: String::from(DEFAULT_LIST_NAME), todos: format::parse_plaintext(&file_content) }]
+            }
+        };
+
+        if tdo.lists.is_empty() {
+            tdo.lists.push(TodoList::new(String::from(DEFAULT_LIST_NAME)));
+        }
+
+        Ok(tdo)
+    }
+
+    fn write_todos(tdo: &Tdo, path: &String) -> Result<(), TdoError> {
+        let serialized = match file_format(path) {
+            FileFormat::Json => serde_json::to_string(&tdo)
+                .map_err(|e| TdoError::Io(std::io::Error::other(e)))?,
+            FileFormat::Markdown => format::to_markdown(&all_todos(tdo)),
+            FileFormat::Plaintext => format::to_plaintext(&all_todos(tdo))
+        };
+
+        std::fs::write(path, serialized)?;
+        Ok(())
+    }
+}
+
+/// Loads the todo store from `path`, recovering from a corrupt file
+/// instead of panicking or silently discarding it.
+fn load_todos(path: &str) -> Result<Tdo, TdoError> {
+    match FileProvider::read_todos(&path.to_string()) {
+        Ok(tdo) => Ok(tdo),
+        Err(TdoError::Deserialize { path, source }) => {
+            println!("{} could not be parsed as todos: {}", path, source);
+
+            let choices = vec!["back up the file and start fresh", "exit without changing anything"];
+            let choice = get_option(&choices, Some("How would you like to recover?"));
+
+            if choice == 1 {
+                std::process::exit(1);
+            }
+
+            let backup_path = format!("{}.bak", path);
+            std::fs::rename(&path, &backup_path)?;
+            File::create(&path)?;
 
-        let ts = serde_json::from_str(&file_content).unwrap_or(Vec::new());
+            println!("Backed up the corrupt file to {}", backup_path);
 
-        Ok(ts)
+            Ok(Tdo { lists: vec![TodoList::new(String::from(DEFAULT_LIST_NAME))] })
+        },
+        Err(e) => Err(e)
     }
+}
+
+struct RemoteProvider {
+    endpoint: String
+}
 
-    fn write_todos(ts: &Vec<Todo>, path: &String) -> Result<(), Box<dyn std::error::Error>> {
-        std::fs::write(path, serde_json::to_string(&ts)?)?;
+impl RemoteProvider {
+    fn new(endpoint: String) -> Self {
+        Self { endpoint }
+    }
+
+    fn fetch(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        match ureq::get(&self.endpoint).call() {
+            Ok(response) => Ok(response.into_string()?.into_bytes()),
+            Err(ureq::Error::Status(404, _)) => Ok(Vec::new()),
+            Err(e) => Err(e.into())
+        }
+    }
+
+    fn push(&self, bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        ureq::put(&self.endpoint).send_bytes(bytes)?;
         Ok(())
     }
 }
 
+enum SyncOutcome {
+    UpToDate,
+    Pushed,
+    Pulled,
+    Conflict
+}
+
+fn digest(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+fn last_synced_digest_path(path: &str) -> String {
+    format!("{}.synced", path)
+}
+
+fn read_last_synced_digest(path: &str) -> String {
+    std::fs::read_to_string(last_synced_digest_path(path)).unwrap_or_default()
+}
+
+fn write_last_synced_digest(path: &str, digest: &str) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::write(last_synced_digest_path(path), digest)?;
+    Ok(())
+}
+
+fn sync_with_remote(path: &str, remote: &RemoteProvider) -> Result<SyncOutcome, Box<dyn std::error::Error>> {
+    let local_bytes = std::fs::read(path).unwrap_or_default();
+    let local_digest = digest(&local_bytes);
+
+    let remote_bytes = remote.fetch()?;
+
+    if remote_bytes.is_empty() {
+        remote.push(&local_bytes)?;
+        write_last_synced_digest(path, &local_digest)?;
+        return Ok(SyncOutcome::Pushed);
+    }
+
+    if local_bytes.is_empty() {
+        std::fs::write(path, &remote_bytes)?;
+        write_last_synced_digest(path, &digest(&remote_bytes))?;
+        return Ok(SyncOutcome::Pulled);
+    }
+
+    let remote_digest = digest(&remote_bytes);
+
+    let last_synced = read_last_synced_digest(path);
+
+    match (local_digest == last_synced, remote_digest == last_synced) {
+        (true, true) => Ok(SyncOutcome::UpToDate),
+        (false, true) => {
+            remote.push(&local_bytes)?;
+            write_last_synced_digest(path, &local_digest)?;
+            Ok(SyncOutcome::Pushed)
+        },
+        (true, false) => {
+            std::fs::write(path, &remote_bytes)?;
+            write_last_synced_digest(path, &remote_digest)?;
+            Ok(SyncOutcome::Pulled)
+        },
+        (false, false) => Ok(SyncOutcome::Conflict)
+    }
+}
+
+fn resolve_conflict(path: &str, remote: &RemoteProvider) -> Result<(), Box<dyn std::error::Error>> {
+    let local_bytes = std::fs::read(path).unwrap_or_default();
+    let remote_bytes = remote.fetch()?;
+
+    let choices = vec!["keep local copy", "keep remote copy", "merge both copies"];
+    let choice = get_option(&choices, Some("Local and remote todos have both changed, pick how to resolve it"));
+
+    let resolved_bytes = match choice {
+        0 => local_bytes,
+        1 => remote_bytes.clone(),
+        _ => {
+            let local_tdo: Tdo = serde_json::from_slice(&local_bytes)
+                .map_err(|source| TdoError::Deserialize { path: path.to_string(), source })?;
+            let remote_tdo: Tdo = serde_json::from_slice(&remote_bytes)
+                .map_err(|source| TdoError::Deserialize { path: format!("{} (remote)", path), source })?;
+            serde_json::to_vec(&merge_tdo(local_tdo, remote_tdo))?
+        }
+    };
+
+    std::fs::write(path, &resolved_bytes)?;
+    remote.push(&resolved_bytes)?;
+    write_last_synced_digest(path, &digest(&resolved_bytes))?;
+
+    Ok(())
+}
+
+fn merge_tdo(local: Tdo, remote: Tdo) -> Tdo {
+    let mut merged = local;
+
+    for remote_list in remote.lists {
+        match merged.lists.iter_mut().find(|l| l.name == remote_list.name) {
+            Some(existing) => {
+                for todo in remote_list.todos {
+                    if !existing.todos.iter().any(|t| t.id == todo.id) {
+                        existing.todos.push(todo);
+                    }
+                }
+            },
+            None => merged.lists.push(remote_list)
+        }
+    }
+
+    merged
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
-enum Status {
+pub(crate) enum Status {
     Incomplete,
     Done
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub(crate) enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
-struct Todo {
-    id: String,
-    todo: String,
-    status: Status
+pub(crate) struct Todo {
+    pub(crate) id: String,
+    pub(crate) todo: String,
+    pub(crate) status: Status,
+    #[serde(default)]
+    pub(crate) due: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub(crate) priority: Priority
 }
 
 impl Todo {
-    fn new(todo: String) -> Result<Self, &'static str> {
+    fn new(todo: String, due: Option<DateTime<Utc>>, priority: Priority) -> Result<Self, TdoError> {
         if todo.len() == 0 {
-            return Err("todo cannot be empty");
+            return Err(TdoError::EmptyTodo);
         }
 
         Ok(Self {
             id: Uuid::new_v4().to_string(),
             todo,
-            status: Status::Incomplete
+            status: Status::Incomplete,
+            due,
+            priority
         })
     }
 }
 
-fn add_todo(todos: &mut Vec<Todo>) -> Result<&Vec<Todo>, Box<dyn std::error::Error>> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct TodoList {
+    pub(crate) name: String,
+    pub(crate) todos: Vec<Todo>
+}
+
+impl TodoList {
+    pub(crate) fn new(name: String) -> Self {
+        Self { name, todos: Vec::new() }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub(crate) struct Tdo {
+    pub(crate) lists: Vec<TodoList>
+}
+
+fn create_list(tdo: &mut Tdo) {
+    let name = prompt("Enter list name: ");
+    tdo.lists.push(TodoList::new(name));
+    println!("List created");
+}
+
+fn switch_active_list(tdo: &Tdo) -> usize {
+    let list_names: Vec<&str> = tdo.lists.iter().map(|l| l.name.as_str()).collect();
+
+    get_option(&list_names, Some("Switch to which list?"))
+}
+
+fn move_todo(tdo: &mut Tdo, active: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let todo_names: Vec<&str> = tdo.lists[active].todos.iter().map(|t| t.todo.as_str()).collect();
+
+    if todo_names.is_empty() {
+        println!("No todos to move");
+        return Ok(());
+    }
+
+    let todo_idx = get_option(&todo_names, Some("Pick a todo to move"));
+
+    let list_names: Vec<&str> = tdo.lists.iter().map(|l| l.name.as_str()).collect();
+    let dest_idx = get_option(&list_names, Some("Move to which list?"));
+
+    if dest_idx == active {
+        println!("Todo is already in that list");
+        return Ok(());
+    }
+
+    let todo = tdo.lists[active].todos.remove(todo_idx);
+    tdo.lists[dest_idx].todos.push(todo);
+
+    Ok(())
+}
+
+fn import_todos(tdo: &mut Tdo, active: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let path = prompt("Enter path to import from: ");
+
+    if !Path::new(&path).exists() {
+        println!("{} does not exist, nothing imported", path);
+        return Ok(());
+    }
+
+    let imported = FileProvider::read_todos(&path)?;
+
+    let count: usize = imported.lists.iter().map(|l| l.todos.len()).sum();
+    for list in imported.lists {
+        tdo.lists[active].todos.extend(list.todos);
+    }
+
+    println!("Imported {} todos from {}", count, path);
+
+    Ok(())
+}
+
+fn export_todos(tdo: &Tdo, active: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let path = prompt("Enter path to export to: ");
+
+    let export = Tdo {
+        lists: vec![TodoList { name: tdo.lists[active].name.clone(), todos: tdo.lists[active].todos.clone() }]
+    };
+
+    FileProvider::write_todos(&export, &path)?;
+    println!("Exported todos to {}", path);
+
+    Ok(())
+}
+
+fn add_todo(todos: &mut Vec<Todo>) -> Result<&Vec<Todo>, TdoError> {
     let response = prompt("Enter todo: ");
+    let due = prompt_due_date();
+    let priority = prompt_priority();
+
+    add_todo_text(todos, response, due, priority)
+}
+
+fn prompt_due_date() -> Option<DateTime<Utc>> {
+    let input = prompt("Enter due date as YYYY-MM-DD, or leave blank for none: ");
+
+    if input.is_empty() {
+        return None;
+    }
 
-    let new_todo = Todo::new(response)?;
+    chrono::NaiveDate::parse_from_str(&input, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|naive| naive.and_utc())
+}
+
+fn prompt_priority() -> Priority {
+    let choices = vec!["low", "medium", "high"];
+
+    match get_option(&choices, Some("Pick a priority")) {
+        0 => Priority::Low,
+        2 => Priority::High,
+        _ => Priority::Medium
+    }
+}
+
+pub(crate) fn add_todo_text(todos: &mut Vec<Todo>, text: String, due: Option<DateTime<Utc>>, priority: Priority) -> Result<&Vec<Todo>, TdoError> {
+    let new_todo = Todo::new(text, due, priority)?;
     // let mut todos = get_todos()?;
     todos.push(new_todo);
     // let result = write_file(&todos)?;
     Ok(todos)
 }
 
-fn complete_todo(id: &str, todos: &mut Vec<Todo>) -> Result<bool, Box<dyn std::error::Error>> {
+pub(crate) fn complete_todo(id: &str, todos: &mut Vec<Todo>) -> Result<bool, TdoError> {
     for t in todos {
-        if t.id == id { 
+        if t.id == id {
             t.status = Status::Done;
             return Ok(true);
-        } else { 
-            t.status = Status::Incomplete;
         }
     }
 
-    Err(Box::new(TodoError(String::from("todo not found"))))
+    Err(TdoError::TodoNotFound(id.to_string()))
 }
 
-fn get_unfinished_todos(ts: &Vec<Todo>) -> Result<Vec<Todo>, Error> {
+pub(crate) fn get_unfinished_todos(ts: &Vec<Todo>) -> Result<Vec<Todo>, TdoError> {
     let mut unfinished: Vec<Todo> = Vec::new();
 
     for t in ts {
@@ -161,12 +659,74 @@ fn get_unfinished_todos(ts: &Vec<Todo>) -> Result<Vec<Todo>, Error> {
     Ok(unfinished)
 }
 
-fn get_todo_to_complete(unfinished_todos: &Vec<Todo>) -> Result<String, Box<dyn std::error::Error>> {
+fn get_unfinished_count(tdo: &Tdo, active: usize, all_lists: bool) -> usize {
+    if all_lists {
+        tdo.lists.iter()
+            .flat_map(|l| l.todos.iter())
+            .filter(|t| t.status == Status::Incomplete)
+            .count()
+    } else {
+        tdo.lists[active].todos.iter()
+            .filter(|t| t.status == Status::Incomplete)
+            .count()
+    }
+}
+
+enum SortBy {
+    DueDate,
+    Priority
+}
+
+fn sorted_todos(todos: &[Todo], sort_by: SortBy) -> Vec<Todo> {
+    let mut sorted = todos.to_vec();
+
+    match sort_by {
+        SortBy::DueDate => sorted.sort_by_key(|t| t.due),
+        SortBy::Priority => sorted.sort_by_key(|t| std::cmp::Reverse(priority_rank(&t.priority)))
+    }
+
+    sorted
+}
+
+fn priority_rank(priority: &Priority) -> u8 {
+    match priority {
+        Priority::Low => 0,
+        Priority::Medium => 1,
+        Priority::High => 2
+    }
+}
+
+fn overdue_todos(todos: &[Todo]) -> Vec<Todo> {
+    let now = Utc::now();
+
+    todos.iter()
+        .filter(|t| t.status == Status::Incomplete)
+        .filter(|t| t.due.is_some_and(|due| due < now))
+        .cloned()
+        .collect()
+}
+
+fn print_todos(label: &str, todos: &[Todo]) {
+    println!("\n-- {} --", label);
+
+    if todos.is_empty() {
+        println!("(none)");
+    }
+
+    for t in todos {
+        let due = t.due.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_else(|| String::from("no due date"));
+        println!("[{:?}] {} (priority: {:?}, due: {})", t.status, t.todo, t.priority, due);
+    }
+}
+
+fn get_todo_to_complete(unfinished_todos: &Vec<Todo>) -> Result<String, TdoError> {
     let todo_names: Vec<&str> = unfinished_todos.iter().map(|t| t.todo.as_str()).collect();
 
     let option = get_option(&todo_names, Some("Pick a todo to mark as complete"));
 
-    Ok(unfinished_todos.get(option).unwrap().id.clone())
+    unfinished_todos.get(option)
+        .map(|t| t.id.clone())
+        .ok_or(TdoError::InvalidOption)
 }
 
 fn prompt(message: &str) -> String {
@@ -186,15 +746,6 @@ fn prompt(message: &str) -> String {
     input.to_string()
 }
 
-fn list_options(todo_count: usize) -> Vec<&'static str> {
-    let options = vec!["add a new todo", "complete a todo"];
-    if todo_count == 0 {
-        vec![options[0]]
-    }else {
-        options
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -202,20 +753,72 @@ mod tests {
     #[test]
     fn nonexistent_file() {
         let path = "/tmp/notfound";
-        let file = FileProvider::read_todos(&String::from(path)).unwrap();
-        
+        let tdo = FileProvider::read_todos(&String::from(path)).unwrap();
+
         assert!(Path::new(path).exists());
-        assert_eq!(0, file.len());
+        assert_eq!(1, tdo.lists.len());
+        assert_eq!(0, tdo.lists[0].todos.len());
     }
 
     #[test]
-    fn list_options_zero() {
-        assert_eq!(1, list_options(0).len());
+    fn reload_pending_is_false_with_no_events() {
+        let (_tx, rx) = channel();
+
+        assert!(!reload_pending(&rx));
     }
 
     #[test]
-    fn list_options_one() {
-        assert_eq!(2, list_options(1).len());
+    fn digest_is_stable_for_same_bytes() {
+        assert_eq!(digest(b"hello"), digest(b"hello"));
+        assert_ne!(digest(b"hello"), digest(b"world"));
+    }
+
+    #[test]
+    fn merge_tdo_unions_lists_and_dedupes_by_id() {
+        let local = Tdo {
+            lists: vec![
+                TodoList {
+                    name: String::from("default"),
+                    todos: vec![Todo { id: String::from("1"), todo: String::from("a"), status: Status::Incomplete, due: None, priority: Priority::Medium }]
+                }
+            ]
+        };
+
+        let remote = Tdo {
+            lists: vec![
+                TodoList {
+                    name: String::from("default"),
+                    todos: vec![
+                        Todo { id: String::from("1"), todo: String::from("a"), status: Status::Incomplete, due: None, priority: Priority::Medium },
+                        Todo { id: String::from("2"), todo: String::from("b"), status: Status::Incomplete, due: None, priority: Priority::Medium }
+                    ]
+                },
+                TodoList {
+                    name: String::from("work"),
+                    todos: vec![Todo { id: String::from("3"), todo: String::from("c"), status: Status::Incomplete, due: None, priority: Priority::Medium }]
+                }
+            ]
+        };
+
+        let merged = merge_tdo(local, remote);
+
+        assert_eq!(2, merged.lists.len());
+        assert_eq!(2, merged.lists[0].todos.len());
+    }
+
+    #[test]
+    fn build_menu_no_unfinished_single_list() {
+        assert_eq!(7, build_menu(0, 1).len());
+    }
+
+    #[test]
+    fn build_menu_unfinished_single_list() {
+        assert_eq!(8, build_menu(1, 1).len());
+    }
+
+    #[test]
+    fn build_menu_unfinished_multiple_lists() {
+        assert_eq!(10, build_menu(1, 2).len());
     }
 
     #[test]
@@ -224,20 +827,107 @@ mod tests {
             Todo {
                 id: String::from("1"),
                 todo: String::from("todo one"),
-                status: Status::Done
+                status: Status::Done,
+                due: None,
+                priority: Priority::Medium
             },
             Todo {
                 id: String::from("2"),
                 todo: String::from("todo two"),
-                status: Status::Incomplete
+                status: Status::Incomplete,
+                due: None,
+                priority: Priority::Medium
             },
             Todo {
                 id: String::from("3"),
                 todo: String::from("todo three"),
-                status: Status::Incomplete
+                status: Status::Incomplete,
+                due: None,
+                priority: Priority::Medium
             }
         ];
 
         assert_eq!(2, get_unfinished_todos(&todos).unwrap().len());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn count_unfinished_across_all_lists() {
+        let tdo = Tdo {
+            lists: vec![
+                TodoList {
+                    name: String::from("one"),
+                    todos: vec![
+                        Todo { id: String::from("1"), todo: String::from("a"), status: Status::Incomplete, due: None, priority: Priority::Medium }
+                    ]
+                },
+                TodoList {
+                    name: String::from("two"),
+                    todos: vec![
+                        Todo { id: String::from("2"), todo: String::from("b"), status: Status::Incomplete, due: None, priority: Priority::Medium },
+                        Todo { id: String::from("3"), todo: String::from("c"), status: Status::Done, due: None, priority: Priority::Medium }
+                    ]
+                }
+            ]
+        };
+
+        assert_eq!(2, get_unfinished_count(&tdo, 0, true));
+        assert_eq!(1, get_unfinished_count(&tdo, 1, false));
+    }
+
+    #[test]
+    fn new_todo_rejects_empty_text() {
+        assert!(matches!(Todo::new(String::new(), None, Priority::Medium), Err(TdoError::EmptyTodo)));
+    }
+
+    #[test]
+    fn complete_todo_reports_missing_id() {
+        let mut todos = vec![Todo { id: String::from("1"), todo: String::from("a"), status: Status::Incomplete, due: None, priority: Priority::Medium }];
+
+        let err = complete_todo("missing", &mut todos).unwrap_err();
+
+        assert!(matches!(err, TdoError::TodoNotFound(id) if id == "missing"));
+    }
+
+    #[test]
+    fn complete_todo_leaves_other_todos_untouched() {
+        let mut todos = vec![
+            Todo { id: String::from("1"), todo: String::from("a"), status: Status::Incomplete, due: None, priority: Priority::Medium },
+            Todo { id: String::from("2"), todo: String::from("b"), status: Status::Incomplete, due: None, priority: Priority::Medium }
+        ];
+
+        complete_todo("2", &mut todos).unwrap();
+
+        assert_eq!(Status::Incomplete, todos[0].status);
+        assert_eq!(Status::Done, todos[1].status);
+    }
+
+    #[test]
+    fn sorts_by_priority_highest_first() {
+        let todos = vec![
+            Todo { id: String::from("1"), todo: String::from("low"), status: Status::Incomplete, due: None, priority: Priority::Low },
+            Todo { id: String::from("2"), todo: String::from("high"), status: Status::Incomplete, due: None, priority: Priority::High }
+        ];
+
+        let sorted = sorted_todos(&todos, SortBy::Priority);
+
+        assert_eq!("high", sorted[0].todo);
+        assert_eq!("low", sorted[1].todo);
+    }
+
+    #[test]
+    fn overdue_todos_excludes_done_and_future_due_dates() {
+        let past = Utc::now() - chrono::Duration::days(1);
+        let future = Utc::now() + chrono::Duration::days(1);
+
+        let todos = vec![
+            Todo { id: String::from("1"), todo: String::from("overdue"), status: Status::Incomplete, due: Some(past), priority: Priority::Medium },
+            Todo { id: String::from("2"), todo: String::from("not due yet"), status: Status::Incomplete, due: Some(future), priority: Priority::Medium },
+            Todo { id: String::from("3"), todo: String::from("done"), status: Status::Done, due: Some(past), priority: Priority::Medium }
+        ];
+
+        let overdue = overdue_todos(&todos);
+
+        assert_eq!(1, overdue.len());
+        assert_eq!("overdue", overdue[0].todo);
+    }
+}
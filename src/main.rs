@@ -1,5 +1,5 @@
 use std::env;
-use todo_rust::{run, Config};
+use todo_rust::{run, serve, Config};
 
 const PATH: &'static str = "todos.json";
 
@@ -9,9 +9,25 @@ fn main() {
         Err(_) => String::from(PATH)
     };
 
-    match run(Config { path }) {
+    let args: Vec<String> = env::args().collect();
+
+    if args.iter().any(|arg| arg == "--serve") {
+        if let Err(e) = serve(path) {
+            println!("{}", e);
+        }
+        return;
+    }
+
+    let watch = args.iter().any(|arg| arg == "--watch");
+
+    let remote = args.iter()
+        .position(|arg| arg == "--remote")
+        .and_then(|idx| args.get(idx + 1))
+        .cloned();
+
+    match run(Config { path, watch, remote }) {
         Ok(_) => println!("done"),
         Err(e) => println!("{}", e)
     }
-    
+
 }
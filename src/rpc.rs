@@ -0,0 +1,190 @@
+use std::io::{BufRead, Write};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use crate::{
+    add_todo_text, complete_todo, get_unfinished_todos, DataProvider, FileProvider, Priority,
+    TodoList, DEFAULT_LIST_NAME
+};
+
+/// Runs a long-lived JSON-RPC 2.0 server over stdin/stdout, framed the way a
+/// language server is: `Content-Length: N\r\n\r\n<json-body>`.
+pub fn serve(path: String) -> Result<(), Box<dyn std::error::Error>> {
+    let stdin = std::io::stdin();
+    let mut reader = stdin.lock();
+    let stdout = std::io::stdout();
+
+    loop {
+        let body = match read_message(&mut reader)? {
+            Some(body) => body,
+            None => break
+        };
+
+        let request: Value = match serde_json::from_str(&body) {
+            Ok(request) => request,
+            Err(e) => {
+                let parse_error = json!({
+                    "jsonrpc": "2.0",
+                    "id": Value::Null,
+                    "error": { "code": -32700, "message": format!("parse error: {}", e) }
+                });
+                write_message(&mut stdout.lock(), &parse_error)?;
+                continue;
+            }
+        };
+        let response = dispatch(&path, &request);
+
+        write_message(&mut stdout.lock(), &response)?;
+    }
+
+    Ok(())
+}
+
+fn read_message(reader: &mut impl BufRead) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut content_length: Option<usize> = None;
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let trimmed = line.trim_end();
+        if trimmed.is_empty() {
+            break;
+        }
+
+        if let Some(value) = trimmed.strip_prefix("Content-Length:") {
+            content_length = Some(value.trim().parse()?);
+        }
+    }
+
+    let len = content_length.ok_or("missing Content-Length header")?;
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+
+    Ok(Some(String::from_utf8(buf)?))
+}
+
+fn write_message(writer: &mut impl Write, body: &Value) -> Result<(), Box<dyn std::error::Error>> {
+    let payload = serde_json::to_string(body)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", payload.len(), payload)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn dispatch(path: &str, request: &Value) -> Value {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "todos/list" => rpc_list(path, &params),
+        "todos/listUnfinished" => rpc_list_unfinished(path, &params),
+        "todos/add" => rpc_add(path, &params),
+        "todos/complete" => rpc_complete(path, &params),
+        _ => Err(format!("unknown method: {}", method).into())
+    };
+
+    match result {
+        Ok(value) => json!({ "jsonrpc": "2.0", "id": id, "result": value }),
+        Err(e) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32000, "message": e.to_string() } })
+    }
+}
+
+fn list_name(params: &Value) -> String {
+    params.get("list")
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_LIST_NAME)
+        .to_string()
+}
+
+fn rpc_list(path: &str, params: &Value) -> Result<Value, Box<dyn std::error::Error>> {
+    let tdo = FileProvider::read_todos(&path.to_string())?;
+    let name = list_name(params);
+
+    let list = tdo.lists.iter().find(|l| l.name == name).ok_or("list not found")?;
+
+    Ok(serde_json::to_value(&list.todos)?)
+}
+
+fn rpc_list_unfinished(path: &str, params: &Value) -> Result<Value, Box<dyn std::error::Error>> {
+    let tdo = FileProvider::read_todos(&path.to_string())?;
+    let name = list_name(params);
+
+    let list = tdo.lists.iter().find(|l| l.name == name).ok_or("list not found")?;
+
+    Ok(serde_json::to_value(&get_unfinished_todos(&list.todos)?)?)
+}
+
+fn parse_due(params: &Value) -> Result<Option<DateTime<Utc>>, Box<dyn std::error::Error>> {
+    match params.get("due").and_then(Value::as_str) {
+        Some(s) => Ok(Some(DateTime::parse_from_rfc3339(s)?.with_timezone(&Utc))),
+        None => Ok(None)
+    }
+}
+
+fn parse_priority(params: &Value) -> Priority {
+    match params.get("priority").and_then(Value::as_str) {
+        Some("low") => Priority::Low,
+        Some("high") => Priority::High,
+        _ => Priority::Medium
+    }
+}
+
+fn rpc_add(path: &str, params: &Value) -> Result<Value, Box<dyn std::error::Error>> {
+    let mut tdo = FileProvider::read_todos(&path.to_string())?;
+    let name = list_name(params);
+    let text = params.get("todo").and_then(Value::as_str).ok_or("missing todo text")?.to_string();
+    let due = parse_due(params)?;
+    let priority = parse_priority(params);
+
+    if !tdo.lists.iter().any(|l| l.name == name) {
+        tdo.lists.push(TodoList::new(name.clone()));
+    }
+
+    let list = tdo.lists.iter_mut().find(|l| l.name == name).unwrap();
+    add_todo_text(&mut list.todos, text, due, priority)?;
+
+    FileProvider::write_todos(&tdo, &path.to_string())?;
+
+    Ok(json!({ "added": true }))
+}
+
+fn rpc_complete(path: &str, params: &Value) -> Result<Value, Box<dyn std::error::Error>> {
+    let mut tdo = FileProvider::read_todos(&path.to_string())?;
+    let name = list_name(params);
+    let id = params.get("id").and_then(Value::as_str).ok_or("missing id")?;
+
+    let list = tdo.lists.iter_mut().find(|l| l.name == name).ok_or("list not found")?;
+    complete_todo(id, &mut list.todos)?;
+
+    FileProvider::write_todos(&tdo, &path.to_string())?;
+
+    Ok(json!({ "completed": true }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_a_framed_message() {
+        let payload = r#"{"jsonrpc":"2.0","method":"todos/list","id":1}"#;
+        let framed = format!("Content-Length: {}\r\n\r\n{}", payload.len(), payload);
+
+        let mut reader = framed.as_bytes();
+        let body = read_message(&mut reader).unwrap().unwrap();
+
+        assert_eq!(payload, body);
+    }
+
+    #[test]
+    fn dispatch_reports_unknown_method() {
+        let request: Value = serde_json::from_str(r#"{"jsonrpc":"2.0","method":"todos/bogus","id":1}"#).unwrap();
+
+        let response = dispatch("/tmp/does-not-matter", &request);
+
+        assert!(response.get("error").is_some());
+    }
+}